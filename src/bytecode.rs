@@ -0,0 +1,298 @@
+//! A compact on-disk format for the optimized op stream produced by
+//! [`parser::ops`](crate::parser::ops).
+//!
+//! Parsing a script already bakes the bracket jump offsets into each
+//! [`Op`], so serializing the result lets a program be shipped or cached
+//! in precompiled form and loaded again without re-running `collapse` or
+//! `calculate_jmp`. Each basic op is encoded as a single opcode byte followed
+//! by its repeat count as an unsigned LEB128 varint, behind a short
+//! magic-and-version header. `SetZero`, `ScanRight` and `ScanLeft` are a bare
+//! opcode byte; `MulAdd` is its opcode byte, the target count as a varint,
+//! then each target as a zigzag varint offset and a factor byte.
+
+use core::{
+	fmt,
+	num::NonZeroUsize,
+};
+
+use alloc::{
+	string::String,
+	vec::Vec,
+};
+
+use crate::{
+	parser::Op,
+	syntax::Token,
+};
+
+/// Magic bytes at the start of every bytecode file: "rainfuck bytecode".
+const MAGIC: [u8; 4] = *b"rfbc";
+/// The format version understood by [`serialize`] and [`load`].
+const VERSION: u8 = 1;
+
+// Opcodes 0..=7 are the basic [`Token`]s (see [`opcode`]); the superinstructions
+// take the bytes above them.
+const OP_SETZERO: u8 = 8;
+const OP_SCANRIGHT: u8 = 9;
+const OP_SCANLEFT: u8 = 10;
+const OP_MULADD: u8 = 11;
+
+/// An error encountered while [`load`]ing a bytecode file.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Error {
+	/// The input does not start with the expected magic bytes.
+	BadMagic,
+	/// The file declares a version this build cannot read.
+	UnsupportedVersion(u8),
+	/// An opcode byte maps to neither a [`Token`] nor a superinstruction.
+	BadOpcode(u8),
+	/// A repeat count was zero, which is never valid.
+	ZeroCount,
+	/// The input ended in the middle of an op or varint, or a varint has more
+	/// continuation bytes than fit in a `usize`.
+	UnexpectedEof,
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str("invalid bytecode: ")?;
+		match *self {
+			Self::BadMagic => f.write_str("missing magic header"),
+			Self::UnsupportedVersion(v) => write!(f, "unsupported version {v}"),
+			Self::BadOpcode(b) => write!(f, "unknown opcode {b:#04x}"),
+			Self::ZeroCount => f.write_str("op with a zero repeat count"),
+			Self::UnexpectedEof => f.write_str("unexpected end of input"),
+		}
+	}
+}
+
+impl core::error::Error for Error {}
+
+fn opcode(t: Token) -> u8 {
+	match t {
+		Token::Left => 0,
+		Token::Right => 1,
+		Token::Add => 2,
+		Token::Sub => 3,
+		Token::Read => 4,
+		Token::Write => 5,
+		Token::LBracket => 6,
+		Token::RBracket => 7,
+	}
+}
+
+fn token(b: u8) -> Option<Token> {
+	Some(match b {
+		0 => Token::Left,
+		1 => Token::Right,
+		2 => Token::Add,
+		3 => Token::Sub,
+		4 => Token::Read,
+		5 => Token::Write,
+		6 => Token::LBracket,
+		7 => Token::RBracket,
+		_ => return None,
+	})
+}
+
+fn name(t: Token) -> &'static str {
+	match t {
+		Token::Left => "Left",
+		Token::Right => "Right",
+		Token::Add => "Add",
+		Token::Sub => "Sub",
+		Token::Read => "Read",
+		Token::Write => "Write",
+		Token::LBracket => "LBracket",
+		Token::RBracket => "RBracket",
+	}
+}
+
+fn write_varint(buf: &mut Vec<u8>, mut n: usize) {
+	loop {
+		let mut byte = (n & 0x7f) as u8;
+		n >>= 7;
+		if n != 0 {
+			byte |= 0x80;
+		}
+		buf.push(byte);
+		if n == 0 {
+			break;
+		}
+	}
+}
+
+fn zigzag(n: isize) -> usize {
+	((n << 1) ^ (n >> (isize::BITS - 1))) as usize
+}
+
+fn unzigzag(u: usize) -> isize {
+	((u >> 1) as isize) ^ -((u & 1) as isize)
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> Result<usize, Error> {
+	let mut result: usize = 0;
+	let mut shift = 0u32;
+	loop {
+		let byte = *data.get(*pos).ok_or(Error::UnexpectedEof)?;
+		*pos += 1;
+		if shift >= usize::BITS {
+			return Err(Error::UnexpectedEof);
+		}
+		result |= ((byte & 0x7f) as usize) << shift;
+		shift += 7;
+		if byte & 0x80 == 0 {
+			break;
+		}
+	}
+	Ok(result)
+}
+
+/// Whether `data` begins with the bytecode magic header, i.e. looks like a
+/// file produced by [`serialize`] rather than brainfuck source.
+pub fn is_bytecode(data: &[u8]) -> bool {
+	data.len() >= MAGIC.len() && data[..MAGIC.len()] == MAGIC
+}
+
+/// Serialize an op stream to the compact bytecode format.
+pub fn serialize(ops: &[Op]) -> Vec<u8> {
+	let mut buf = Vec::with_capacity(MAGIC.len() + 1 + ops.len() * 2);
+	buf.extend_from_slice(&MAGIC);
+	buf.push(VERSION);
+	for op in ops {
+		match op {
+			Op::Basic { t, n } => {
+				buf.push(opcode(*t));
+				write_varint(&mut buf, n.get());
+			}
+			Op::SetZero => buf.push(OP_SETZERO),
+			Op::ScanRight => buf.push(OP_SCANRIGHT),
+			Op::ScanLeft => buf.push(OP_SCANLEFT),
+			Op::MulAdd { targets } => {
+				buf.push(OP_MULADD);
+				write_varint(&mut buf, targets.len());
+				for &(off, k) in targets {
+					write_varint(&mut buf, zigzag(off));
+					buf.push(k);
+				}
+			}
+		}
+	}
+	buf
+}
+
+/// Validate a bytecode file and reconstruct the op stream it encodes.
+///
+/// The jump offsets are read back verbatim, so no re-parsing or bracket
+/// matching is performed.
+pub fn load(data: &[u8]) -> Result<Vec<Op>, Error> {
+	if data.len() < MAGIC.len() + 1 {
+		return Err(Error::UnexpectedEof);
+	}
+	if data[..MAGIC.len()] != MAGIC {
+		return Err(Error::BadMagic);
+	}
+	let version = data[MAGIC.len()];
+	if version != VERSION {
+		return Err(Error::UnsupportedVersion(version));
+	}
+
+	let mut pos = MAGIC.len() + 1;
+	let mut ops = Vec::new();
+	while pos < data.len() {
+		let code = data[pos];
+		pos += 1;
+		let op = match code {
+			OP_SETZERO => Op::SetZero,
+			OP_SCANRIGHT => Op::ScanRight,
+			OP_SCANLEFT => Op::ScanLeft,
+			OP_MULADD => {
+				let len = read_varint(data, &mut pos)?;
+				let mut targets = Vec::with_capacity(len);
+				for _ in 0..len {
+					let off = unzigzag(read_varint(data, &mut pos)?);
+					let k = *data.get(pos).ok_or(Error::UnexpectedEof)?;
+					pos += 1;
+					targets.push((off, k));
+				}
+				Op::MulAdd { targets }
+			}
+			_ => {
+				let t = token(code).ok_or(Error::BadOpcode(code))?;
+				let n =
+					NonZeroUsize::new(read_varint(data, &mut pos)?).ok_or(Error::ZeroCount)?;
+				Op::Basic { t, n }
+			}
+		};
+		ops.push(op);
+	}
+
+	Ok(ops)
+}
+
+/// Pretty-print an op stream, one op per line, with the body of each loop
+/// indented one level deeper than its brackets.
+pub fn disasm(ops: &[Op]) -> String {
+	use fmt::Write;
+
+	let mut out = String::new();
+	let mut depth: usize = 0;
+	for (i, op) in ops.iter().enumerate() {
+		if matches!(op, Op::Basic { t: Token::RBracket, .. }) {
+			depth = depth.saturating_sub(1);
+		}
+		let _ = write!(out, "{i:04}: ");
+		for _ in 0..depth {
+			out.push_str("  ");
+		}
+		match op {
+			Op::Basic { t, n } => {
+				let _ = writeln!(out, "{} n={}", name(*t), n.get());
+			}
+			Op::SetZero => {
+				let _ = writeln!(out, "SetZero");
+			}
+			Op::ScanRight => {
+				let _ = writeln!(out, "ScanRight");
+			}
+			Op::ScanLeft => {
+				let _ = writeln!(out, "ScanLeft");
+			}
+			Op::MulAdd { targets } => {
+				let _ = writeln!(out, "MulAdd {targets:?}");
+			}
+		}
+		if matches!(op, Op::Basic { t: Token::LBracket, .. }) {
+			depth += 1;
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser;
+
+	#[test]
+	fn roundtrip() {
+		// Covers basic ops, MulAdd, SetZero and brackets in one stream.
+		let ops = parser::ops(b"+++[->++<]>.[-]+[.]").unwrap();
+		let back = load(&serialize(&ops)).unwrap();
+		assert_eq!(ops, back);
+	}
+
+	#[test]
+	fn rejects_bad_magic() {
+		assert_eq!(load(b"nope and then some"), Err(Error::BadMagic));
+		assert!(!is_bytecode(b"+++."));
+		assert!(is_bytecode(&serialize(&parser::ops(b"+").unwrap())));
+	}
+
+	#[test]
+	fn disasm_indents_loop_bodies() {
+		// `[.]` survives optimization (it does I/O), so it stays a loop.
+		let ops = parser::ops(b"+[.]").unwrap();
+		assert!(disasm(&ops).contains("  Write n=1"));
+	}
+}
@@ -2,24 +2,29 @@ mod error;
 #[cfg(test)]
 mod tests;
 
-use std::{
+use core::num::NonZeroUsize;
+
+use alloc::{
+	vec,
+	vec::Vec,
+};
+
+pub use self::error::Error;
+use crate::{
 	io::{
 		self,
+		IoError,
 		Read,
 		Write,
 	},
-	num::NonZeroUsize,
-};
-
-pub use self::error::Error;
-use crate::{
 	parser::Op,
 	syntax::Token,
 };
 
-type Result<T> = ::std::result::Result<T, Error>;
+type Result<T> = ::core::result::Result<T, Error>;
 
-#[derive(clap::ValueEnum, Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "std", derive(clap::ValueEnum))]
 pub enum Overflow {
 	/// Overflows wrap around
 	Wrap,
@@ -29,7 +34,8 @@ pub enum Overflow {
 	Check,
 }
 
-#[derive(clap::ValueEnum, Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[cfg_attr(feature = "std", derive(clap::ValueEnum))]
 pub enum EofMode {
 	/// Reads after EOF do nothing
 	Noop,
@@ -39,18 +45,19 @@ pub enum EofMode {
 	Terminate,
 }
 
-impl From<io::Error> for Error {
-	fn from(e: io::Error) -> Self {
+impl From<IoError> for Error {
+	fn from(e: IoError) -> Self {
 		Self::Io(e)
 	}
 }
 
-pub struct Interpreter<I, O> {
+pub struct Interpreter<I, O: Write> {
 	ip: usize,
 	dp: usize,
 	mem: Vec<u8>,
 	ops: Vec<Op>,
 	iobuf: Vec<u8>,
+	outbuf: Vec<u8>,
 
 	input: I,
 	output: O,
@@ -99,6 +106,7 @@ impl<I: Read, O: Write> Interpreter<I, O> {
 			input: io.input,
 			output: io.output,
 			iobuf: Vec::with_capacity(usize::min(max_io, 128)),
+			outbuf: Vec::with_capacity(usize::min(max_io, 128)),
 			cell_overflow_mode: overflow.cell,
 			ptr_overflow_mode: overflow.ptr,
 			max_io: usize::max(max_io, 4),
@@ -109,53 +117,95 @@ impl<I: Read, O: Write> Interpreter<I, O> {
 	}
 
 	pub fn eval(mut self) -> Result<()> {
-		while self.ip < self.ops.len() {
-			let op = self.ops[self.ip];
-			match op.t {
-				Token::Add => self.add(op.n)?,
-				Token::Sub => self.sub(op.n)?,
-				Token::Left => self.left(op.n)?,
-				Token::Right => {
-					self.dp = self
-						.dp
-						.checked_add(op.n.get())
-						.ok_or(Error::RightDpOverflow {
+		// Move the program out of `self` so the loop can borrow an op (and a
+		// `MulAdd`'s targets) while still mutating the rest of the interpreter,
+		// without cloning the op — and its heap-allocated targets — every step.
+		let ops = core::mem::take(&mut self.ops);
+		while self.ip < ops.len() {
+			match &ops[self.ip] {
+				Op::Basic { t, n } => match (*t, *n) {
+					(Token::Add, n) => self.add(n)?,
+					(Token::Sub, n) => self.sub(n)?,
+					(Token::Left, n) => self.left(n)?,
+					(Token::Right, n) => {
+						self.dp = self.dp.checked_add(n.get()).ok_or(Error::RightDpOverflow {
 							from: self.dp,
-							amount: op.n.get(),
+							amount: n.get(),
 						})?
-				}
-				Token::Read => {
-					self.ensure_mem()?;
-					self.mem[self.dp] = self.read(op.n)?;
-				}
-				Token::Write => {
-					let val = *self.mem.get(self.dp).unwrap_or(&0);
-
-					let bytes = [val; 16];
-					let chunks = op.n.get() / 16;
-					let rem = op.n.get() % 16;
-					for _ in 0..chunks {
-						self.output.write(&bytes).map_err(Error::Io)?;
 					}
-					if rem > 0 {
-						self.output.write(&bytes[..rem]).map_err(Error::Io)?;
+					(Token::Read, n) => {
+						self.ensure_mem()?;
+						self.mem[self.dp] = self.read(n)?;
 					}
+					(Token::Write, n) => {
+						let val = *self.mem.get(self.dp).unwrap_or(&0);
+						self.emit(val, n.get())?;
+					}
+					(Token::LBracket, n) => {
+						if self.mem.get(self.dp).map_or(true, |&v| v == 0) {
+							self.ip += n.get();
+						}
+					}
+					(Token::RBracket, n) => {
+						if self.mem.get(self.dp).map_or(false, |&b| b != 0) {
+							self.ip -= n.get();
+						}
+					}
+				},
+				Op::SetZero => {
+					self.ensure_mem()?;
+					*self.value() = 0;
 				}
-				Token::LBracket => {
-					if self.mem.get(self.dp).map_or(true, |&n| n == 0) {
-						self.ip += op.n.get();
+				Op::ScanRight => {
+					while self.mem.get(self.dp).is_some_and(|&b| b != 0) {
+						self.dp = self.dp.checked_add(1).ok_or(Error::RightDpOverflow {
+							from: self.dp,
+							amount: 1,
+						})?;
 					}
 				}
-				Token::RBracket => {
-					if self.mem.get(self.dp).map_or(false, |&b| b != 0) {
-						self.ip -= op.n.get();
+				Op::ScanLeft => {
+					while self.mem.get(self.dp).is_some_and(|&b| b != 0) {
+						self.left(NonZeroUsize::MIN)?;
 					}
 				}
+				Op::MulAdd { targets } => self.mul_add(targets)?,
 			}
 
 			self.ip += 1;
 		}
 
+		self.flush()?;
+		Ok(())
+	}
+
+	/// Execute a [`MulAdd`](Op::MulAdd) superinstruction: scatter a multiple
+	/// of the current cell into each target and then zero the current cell.
+	fn mul_add(&mut self, targets: &[(isize, u8)]) -> Result<()> {
+		self.ensure_mem()?;
+		let base = *self.value() as usize;
+
+		if base != 0 {
+			let origin = self.dp;
+			for &(off, k) in targets {
+				if off >= 0 {
+					self.dp = origin.checked_add(off as usize).ok_or(Error::RightDpOverflow {
+						from: origin,
+						amount: off as usize,
+					})?;
+				} else {
+					self.dp = origin;
+					self.left(NonZeroUsize::new((-off) as usize).expect("offset is nonzero"))?;
+				}
+
+				if let Some(amount) = NonZeroUsize::new(k as usize * base) {
+					self.add(amount)?;
+				}
+			}
+			self.dp = origin;
+		}
+
+		*self.value() = 0;
 		Ok(())
 	}
 
@@ -165,6 +215,9 @@ impl<I: Read, O: Write> Interpreter<I, O> {
 	}
 
 	fn read(&mut self, times: NonZeroUsize) -> Result<u8> {
+		// Make sure any buffered output (e.g. a prompt) reaches the sink
+		// before we block waiting for input.
+		self.flush()?;
 		let times = times.get();
 		// ensure capacity
 		let need = usize::min(self.max_io, times).saturating_sub(self.iobuf.len());
@@ -177,8 +230,7 @@ impl<I: Read, O: Write> Interpreter<I, O> {
 			return match self.eof_mode {
 				EofMode::Noop => Ok(*self.value()),
 				EofMode::Set0 => Ok(0),
-				EofMode::Terminate => Err(Error::Io(io::Error::new(
-					io::ErrorKind::UnexpectedEof,
+				EofMode::Terminate => Err(Error::Io(io::unexpected_eof(
 					"reached end of input but a a read command was executed",
 				))),
 			};
@@ -194,8 +246,7 @@ impl<I: Read, O: Write> Interpreter<I, O> {
 				return match self.eof_mode {
 					EofMode::Noop => Ok(last_read),
 					EofMode::Set0 => Ok(0),
-					EofMode::Terminate => Err(Error::Io(io::Error::new(
-						io::ErrorKind::UnexpectedEof,
+					EofMode::Terminate => Err(Error::Io(io::unexpected_eof(
 						"reached end of input but a a read command was executed",
 					))),
 				};
@@ -314,3 +365,49 @@ impl<I: Read, O: Write> Interpreter<I, O> {
 		Ok(())
 	}
 }
+
+impl<I, O: Write> Interpreter<I, O> {
+	/// Queue `count` copies of `byte` for output, buffering them so the sink
+	/// is hit in bulk rather than once per write command.
+	fn emit(&mut self, byte: u8, count: usize) -> Result<()> {
+		// A run at least as large as the buffer bypasses it entirely and is
+		// handed to `write_repeated` (vectored, under `std`) instead of being
+		// copied in.
+		if count >= self.max_io {
+			self.flush()?;
+			self.output.write_repeated(byte, count)?;
+			return Ok(());
+		}
+
+		let mut remaining = count;
+		while remaining > 0 {
+			let space = self.max_io - self.outbuf.len();
+			let take = remaining.min(space);
+			self.outbuf.resize(self.outbuf.len() + take, byte);
+			remaining -= take;
+			if self.outbuf.len() >= self.max_io {
+				self.flush()?;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Write everything buffered so far and reset the buffer.
+	fn flush(&mut self) -> Result<()> {
+		if !self.outbuf.is_empty() {
+			self.output.write_all(&self.outbuf)?;
+			self.outbuf.clear();
+		}
+		Ok(())
+	}
+}
+
+impl<I, O: Write> Drop for Interpreter<I, O> {
+	fn drop(&mut self) {
+		// Best-effort: `eval` already flushes on success, but a buffer left
+		// behind by an early error still gets one last chance to reach the
+		// sink here.
+		let _ = self.flush();
+	}
+}
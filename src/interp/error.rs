@@ -1,13 +1,12 @@
-use std::{
-	fmt::{self,},
-	io,
-};
+use core::fmt;
+
+use crate::io::IoError;
 
 #[derive(Debug)]
 pub enum Error {
 	RightDpOverflow { from: usize, amount: usize },
 	LeftDpOverflow { from: usize, amount: usize },
-	Io(io::Error),
+	Io(IoError),
 	Oom { have: usize, want: usize },
 	AddOverflow { mem: u8, value: usize },
 	SubOverflow { mem: u8, value: usize },
@@ -39,4 +38,4 @@ impl fmt::Display for Error {
 	}
 }
 
-impl std::error::Error for Error {}
+impl core::error::Error for Error {}
@@ -122,3 +122,92 @@ fn head() {
 
 	assert!(got[expected.len()..].iter().all(|&b| b == 0),);
 }
+
+#[test]
+fn set_zero() {
+	// `[-]` must actually clear the five increments, leaving 0.
+	let mut output = Cursor::new([0; 1]);
+	new(
+		b"+++++[-].",
+		b"",
+		&mut output,
+		OverflowOptions::default(),
+		EofMode::Noop,
+	)
+	.eval()
+	.unwrap();
+
+	assert_eq!(b"\0", &output.into_inner());
+}
+
+#[test]
+fn mul_add() {
+	// 6 * 8 == 48 == b'0', scattered into the neighbouring cell.
+	let mut output = Cursor::new([0; 1]);
+	new(
+		b"++++++[>++++++++<-]>.",
+		b"",
+		&mut output,
+		OverflowOptions::default(),
+		EofMode::Noop,
+	)
+	.eval()
+	.unwrap();
+
+	assert_eq!(b"0", &output.into_inner());
+}
+
+#[test]
+fn scan_right() {
+	// Cells 0..=2 are non-zero; `[>]` from cell 0 lands on the first zero.
+	let mut output = Cursor::new([0; 1]);
+	new(
+		b"+>+>+><<<[>]+.",
+		b"",
+		&mut output,
+		OverflowOptions::default(),
+		EofMode::Noop,
+	)
+	.eval()
+	.unwrap();
+
+	assert_eq!(b"\x01", &output.into_inner());
+}
+
+#[test]
+fn scan_left() {
+	// Cells 1..=3 are non-zero; `[<]` from cell 3 lands back on cell 0.
+	let mut output = Cursor::new([0; 1]);
+	new(
+		b">+>+>+[<]+.",
+		b"",
+		&mut output,
+		OverflowOptions::default(),
+		EofMode::Noop,
+	)
+	.eval()
+	.unwrap();
+
+	assert_eq!(b"\x01", &output.into_inner());
+}
+
+#[test]
+fn buffered_output_flushes_everything() {
+	// max_io is clamped to its minimum of 4 in these tests, so the collapsed
+	// run of ten writes takes the large-run path that bypasses the buffer;
+	// every emitted byte must still arrive.
+	let mut script = vec![b'+'; 33];
+	script.extend_from_slice(&[b'.'; 10]);
+	let mut output = Cursor::new([0; 10]);
+	new(
+		&script,
+		b"",
+		&mut output,
+		OverflowOptions::default(),
+		EofMode::Noop,
+	)
+	.eval()
+	.unwrap();
+
+	assert_eq!(b"!!!!!!!!!!", &output.into_inner());
+}
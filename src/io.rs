@@ -0,0 +1,172 @@
+//! Minimal [`Read`]/[`Write`] abstractions so the parser and interpreter can
+//! run on `no_std` targets where [`std::io`] is unavailable.
+//!
+//! With the `std` feature enabled (the default) [`IoError`] aliases
+//! [`std::io::Error`] and blanket impls forward to the matching [`std::io`]
+//! traits, so any `std` reader or writer can be handed to the interpreter
+//! unchanged. Without it, downstream code implements [`Read`]/[`Write`]
+//! directly and errors are carried by the crate's own [`IoError`].
+
+#[cfg(feature = "std")]
+pub use std::io::Error as IoError;
+
+#[cfg(not(feature = "std"))]
+pub use self::imp::{
+	ErrorKind,
+	IoError,
+};
+
+/// The reading half of the interpreter's I/O.
+pub trait Read {
+	/// Pull some bytes into `buf`, returning how many were read.
+	fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError>;
+}
+
+/// The writing half of the interpreter's I/O.
+pub trait Write {
+	/// Write some bytes from `buf`, returning how many were written.
+	fn write(&mut self, buf: &[u8]) -> Result<usize, IoError>;
+
+	/// Write the whole buffer, retrying until it has all been written.
+	fn write_all(&mut self, mut buf: &[u8]) -> Result<(), IoError> {
+		while !buf.is_empty() {
+			match self.write(buf)? {
+				0 => return Err(write_zero()),
+				n => buf = &buf[n..],
+			}
+		}
+		Ok(())
+	}
+
+	/// Write `count` copies of `byte`.
+	///
+	/// The default reuses a small stack scratch buffer; the `std` impl
+	/// overrides this to issue vectored writes so a long run collapses into
+	/// as few syscalls as possible.
+	fn write_repeated(&mut self, byte: u8, count: usize) -> Result<(), IoError> {
+		let scratch = [byte; 64];
+		let mut remaining = count;
+		while remaining > 0 {
+			let take = remaining.min(scratch.len());
+			self.write_all(&scratch[..take])?;
+			remaining -= take;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Read> Read for T {
+	fn read(&mut self, buf: &mut [u8]) -> Result<usize, IoError> {
+		std::io::Read::read(self, buf)
+	}
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Write> Write for T {
+	fn write(&mut self, buf: &[u8]) -> Result<usize, IoError> {
+		std::io::Write::write(self, buf)
+	}
+
+	fn write_repeated(&mut self, byte: u8, count: usize) -> Result<(), IoError> {
+		use std::io::IoSlice;
+
+		use alloc::vec::Vec;
+
+		// One filled scratch buffer, pointed at by many `IoSlice`s, lets a run
+		// of identical bytes go out as a single vectored write instead of one
+		// copy per chunk. All bytes are equal, so a short write just leaves
+		// fewer bytes `remaining` — no per-slice bookkeeping is needed.
+		const CHUNK: usize = 256;
+		const MAX_SLICES: usize = 1024;
+		let scratch = [byte; CHUNK];
+
+		let mut remaining = count;
+		while remaining > 0 {
+			let full = remaining / CHUNK;
+			let rem = remaining % CHUNK;
+			let n_full = full.min(MAX_SLICES);
+
+			let mut slices: Vec<IoSlice> = Vec::with_capacity(n_full + 1);
+			slices.resize(n_full, IoSlice::new(&scratch));
+			if n_full == full && rem > 0 {
+				slices.push(IoSlice::new(&scratch[..rem]));
+			}
+
+			match std::io::Write::write_vectored(self, &slices)? {
+				0 => return Err(write_zero()),
+				n => remaining -= n,
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Build the "read past end of input" error in a way that works on both the
+/// `std` and `no_std` paths.
+pub(crate) fn unexpected_eof(msg: &'static str) -> IoError {
+	#[cfg(feature = "std")]
+	{
+		std::io::Error::new(std::io::ErrorKind::UnexpectedEof, msg)
+	}
+	#[cfg(not(feature = "std"))]
+	{
+		IoError::new(ErrorKind::UnexpectedEof, msg)
+	}
+}
+
+/// Build the "wrote zero bytes" error returned by [`Write::write_all`] and
+/// [`Write::write_repeated`] when the sink stops accepting data.
+fn write_zero() -> IoError {
+	#[cfg(feature = "std")]
+	{
+		std::io::Error::new(std::io::ErrorKind::WriteZero, "failed to write whole buffer")
+	}
+	#[cfg(not(feature = "std"))]
+	{
+		IoError::new(ErrorKind::WriteZero, "failed to write whole buffer")
+	}
+}
+
+#[cfg(not(feature = "std"))]
+mod imp {
+	use core::fmt;
+
+	/// A minimal analogue of [`std::io::ErrorKind`].
+	#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+	#[non_exhaustive]
+	pub enum ErrorKind {
+		UnexpectedEof,
+		WriteZero,
+		Other,
+	}
+
+	/// The error returned by [`Read`](super::Read)/[`Write`](super::Write)
+	/// implementations when the `std` feature is disabled.
+	#[derive(Clone, Eq, PartialEq, Debug)]
+	pub struct IoError {
+		kind: ErrorKind,
+		msg: &'static str,
+	}
+
+	impl IoError {
+		/// Create an error from a [`ErrorKind`] and a static message.
+		pub fn new(kind: ErrorKind, msg: &'static str) -> Self {
+			Self { kind, msg }
+		}
+
+		/// The category of this error.
+		pub fn kind(&self) -> ErrorKind {
+			self.kind
+		}
+	}
+
+	impl fmt::Display for IoError {
+		fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+			f.write_str(self.msg)
+		}
+	}
+
+	impl core::error::Error for IoError {}
+}
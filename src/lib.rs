@@ -0,0 +1,9 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod bytecode;
+pub mod interp;
+pub mod io;
+pub mod parser;
+pub mod syntax;
@@ -1,7 +1,3 @@
-mod interp;
-mod parser;
-mod syntax;
-
 use std::{
 	fs::{
 		self,
@@ -15,23 +11,56 @@ use std::{
 	},
 };
 
-use clap::Parser;
-
-use self::interp::{
-	EofMode,
-	Interpreter,
-	Io,
-	Overflow,
-	OverflowOptions,
+use clap::{
+	Args as ClapArgs,
+	Parser,
+	Subcommand,
+};
+use rainfuck::{
+	bytecode,
+	interp::{
+		EofMode,
+		Interpreter,
+		Io,
+		Overflow,
+		OverflowOptions,
+	},
+	parser,
 };
 
 #[derive(Parser)]
 #[command(version)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 /// Execute a brainfuck script
 struct Args {
+	#[command(subcommand)]
+	command: Option<Command>,
+	#[command(flatten)]
+	run: RunArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+	/// Compile a script to a precompiled bytecode file
+	Compile {
+		/// The brainfuck script
+		file: String,
+		#[arg(short, long)]
+		/// The output file
+		out: String,
+	},
+	/// Disassemble a script, printing the optimizer's op stream
+	Disasm {
+		/// The brainfuck script
+		file: String,
+	},
+}
+
+#[derive(ClapArgs)]
+struct RunArgs {
 	#[arg()]
-	/// The brainfuck script
-	file: String,
+	/// The brainfuck script (source, or a precompiled bytecode file)
+	file: Option<String>,
 	#[arg(short, long, default_value = "-")]
 	/// The input file ("-" for stdin)
 	input: String,
@@ -58,10 +87,18 @@ struct Args {
 	eof_mode: EofMode,
 }
 
-fn run() -> Result<(), Box<dyn std::error::Error>> {
-	let args = Args::parse();
-	let code = fs::read(&args.file)?;
-	let ops = parser::ops(&code)?;
+fn run(args: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+	let file = args
+		.file
+		.ok_or("the following required argument was not provided: <FILE>")?;
+	let code = fs::read(&file)?;
+	// A precompiled bytecode file is loaded directly; anything else is
+	// tokenized as brainfuck source.
+	let ops = if bytecode::is_bytecode(&code) {
+		bytecode::load(&code)?
+	} else {
+		parser::ops(&code)?
+	};
 	let mut stdout;
 	let mut stdin;
 	let mut out_file;
@@ -99,8 +136,31 @@ fn run() -> Result<(), Box<dyn std::error::Error>> {
 	Ok(())
 }
 
+fn compile(file: &str, out: &str) -> Result<(), Box<dyn std::error::Error>> {
+	let code = fs::read(file)?;
+	let ops = parser::ops(&code)?;
+	fs::write(out, bytecode::serialize(&ops))?;
+	Ok(())
+}
+
+fn disasm(file: &str) -> Result<(), Box<dyn std::error::Error>> {
+	let code = fs::read(file)?;
+	let ops = parser::ops(&code)?;
+	print!("{}", bytecode::disasm(&ops));
+	Ok(())
+}
+
+fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+	let args = Args::parse();
+	match args.command {
+		Some(Command::Compile { file, out }) => compile(&file, &out),
+		Some(Command::Disasm { file }) => disasm(&file),
+		None => run(args.run),
+	}
+}
+
 fn main() {
-	if let Err(e) = run() {
+	if let Err(e) = try_main() {
 		eprintln!("error: {e:?}");
 		std::process::exit(1);
 	}
@@ -1,4 +1,4 @@
-use std::{
+use core::{
 	fmt::{
 		self,
 		Write,
@@ -6,6 +6,12 @@ use std::{
 	num::NonZeroUsize,
 };
 
+use alloc::{
+	boxed::Box,
+	string::String,
+	vec::Vec,
+};
+
 use crate::syntax::{
 	Token,
 	TokenSpan,
@@ -62,7 +68,7 @@ impl fmt::Debug for Error {
 	}
 }
 
-impl std::error::Error for Error {}
+impl core::error::Error for Error {}
 
 impl Error {
 	fn new(kind: ErrorKind, pos: usize, code: &[u8]) -> Self {
@@ -125,10 +131,22 @@ impl Error {
 	}
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
-pub struct Op {
-	pub t: Token,
-	pub n: NonZeroUsize,
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Op {
+	/// A basic token repeated `n` times. For `LBracket`/`RBracket`, `n` is
+	/// the relative distance to the matching bracket.
+	Basic { t: Token, n: NonZeroUsize },
+	/// `[-]`: set the current cell to zero. `[+]` is not rewritten into this
+	/// and stays an ordinary loop.
+	SetZero,
+	/// An affine loop such as `[->+<]`: for every `(offset, factor)` add
+	/// `factor * mem[dp]` to `mem[dp + offset]`, then zero `mem[dp]`. A
+	/// no-op when `mem[dp]` is already zero.
+	MulAdd { targets: Vec<(isize, u8)> },
+	/// `[>]`: advance `dp` to the next zero cell.
+	ScanRight,
+	/// `[<]`: move `dp` back to the previous zero cell.
+	ScanLeft,
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
@@ -224,12 +242,195 @@ pub fn ops(code: &[u8]) -> Result<Vec<Op>, Error> {
 		cutoff = irs[0].n + 1;
 	}
 
-	Ok(irs
+	let ops = irs
 		.into_iter()
 		.skip(cutoff)
-		.map(|x| Op {
+		.map(|x| Op::Basic {
 			t: x.t.token,
 			n: NonZeroUsize::new(x.n).expect("assertion failed"),
 		})
-		.collect())
+		.collect();
+
+	Ok(superoptimize(ops))
+}
+
+/// Rewrite recognized idiomatic loops into superinstructions.
+///
+/// Only innermost loops (those whose body contains no further brackets and
+/// no I/O) are candidates; everything else is copied through unchanged.
+/// Because collapsing a loop shifts the ops that follow it, the bracket
+/// jump offsets are recomputed afterwards by [`relink`].
+fn superoptimize(ops: Vec<Op>) -> Vec<Op> {
+	let mut out: Vec<Op> = Vec::with_capacity(ops.len());
+	let mut i = 0;
+	while i < ops.len() {
+		if let Op::Basic {
+			t: Token::LBracket,
+			n,
+		} = &ops[i]
+		{
+			let rb = i + n.get();
+			if let Some(op) = recognize(&ops[i + 1..rb]) {
+				out.push(op);
+				i = rb + 1;
+				continue;
+			}
+		}
+
+		out.push(ops[i].clone());
+		i += 1;
+	}
+
+	relink(&mut out);
+	out
+}
+
+fn add_delta(deltas: &mut Vec<(isize, i32)>, offset: isize, d: i32) {
+	if let Some(entry) = deltas.iter_mut().find(|(off, _)| *off == offset) {
+		entry.1 += d;
+	} else {
+		deltas.push((offset, d));
+	}
+}
+
+/// Classify the body of an innermost loop, returning the superinstruction it
+/// is equivalent to, or `None` if it has no faster form.
+fn recognize(body: &[Op]) -> Option<Op> {
+	let mut offset: isize = 0;
+	let mut deltas: Vec<(isize, i32)> = Vec::new();
+
+	for op in body {
+		let (t, n) = match op {
+			Op::Basic { t, n } => (*t, n.get()),
+			// a superinstruction in the body means this was not innermost
+			_ => return None,
+		};
+		match t {
+			Token::Right => offset += n as isize,
+			Token::Left => offset -= n as isize,
+			Token::Add => add_delta(&mut deltas, offset, n as i32),
+			Token::Sub => add_delta(&mut deltas, offset, -(n as i32)),
+			// I/O or a bracket disqualifies the loop
+			_ => return None,
+		}
+	}
+
+	// Pure-movement loops: `[>]` / `[<]`.
+	if deltas.is_empty() {
+		return match (body.len(), offset) {
+			(1, 1) => Some(Op::ScanRight),
+			(1, -1) => Some(Op::ScanLeft),
+			_ => None,
+		};
+	}
+
+	// The remaining forms only terminate when the loop is balanced.
+	if offset != 0 {
+		return None;
+	}
+
+	// The current cell must count down by exactly one per iteration. The
+	// `[+]` form is deliberately *not* recognized: it only zeroes under
+	// wrapping arithmetic, whereas `[-]` is correct under every overflow mode.
+	let d0 = deltas.iter().find(|&&(off, _)| off == 0).map(|&(_, d)| d);
+	if d0 != Some(-1) {
+		return None;
+	}
+
+	// `[-]`: only the current cell is touched.
+	if deltas.len() == 1 {
+		return Some(Op::SetZero);
+	}
+
+	// Affine loop: every other cell receives a positive multiple of the
+	// current cell.
+	let mut targets = Vec::with_capacity(deltas.len() - 1);
+	for &(off, d) in &deltas {
+		if off == 0 {
+			continue;
+		}
+		let k = u8::try_from(d).ok()?;
+		if k == 0 {
+			return None;
+		}
+		targets.push((off, k));
+	}
+
+	Some(Op::MulAdd { targets })
+}
+
+/// Recompute the relative jump offset baked into each surviving bracket pair.
+fn relink(ops: &mut [Op]) {
+	let mut stack: Vec<usize> = Vec::new();
+	for i in 0..ops.len() {
+		match &ops[i] {
+			Op::Basic {
+				t: Token::LBracket,
+				..
+			} => stack.push(i),
+			Op::Basic {
+				t: Token::RBracket,
+				..
+			} => {
+				if let Some(lb) = stack.pop() {
+					let dist = NonZeroUsize::new(i - lb).expect("bracket distance is nonzero");
+					ops[lb] = Op::Basic {
+						t: Token::LBracket,
+						n: dist,
+					};
+					ops[i] = Op::Basic {
+						t: Token::RBracket,
+						n: dist,
+					};
+				}
+			}
+			_ => (),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn basic(t: Token, n: usize) -> Op {
+		Op::Basic {
+			t,
+			n: NonZeroUsize::new(n).expect("nonzero"),
+		}
+	}
+
+	#[test]
+	fn set_zero() {
+		// `[-]` clears; a leading `+` keeps the loop from being cut as dead.
+		assert_eq!(ops(b"+[-]").unwrap(), vec![basic(Token::Add, 1), Op::SetZero]);
+	}
+
+	#[test]
+	fn increment_loop_is_not_set_zero() {
+		// `[+]` only zeroes under wrapping arithmetic, so it stays a loop.
+		assert_eq!(
+			ops(b"+[+]").unwrap(),
+			vec![
+				basic(Token::Add, 1),
+				basic(Token::LBracket, 2),
+				basic(Token::Add, 1),
+				basic(Token::RBracket, 2),
+			],
+		);
+	}
+
+	#[test]
+	fn mul_add() {
+		assert_eq!(
+			ops(b"+[->++<]").unwrap(),
+			vec![basic(Token::Add, 1), Op::MulAdd { targets: vec![(1, 2)] }],
+		);
+	}
+
+	#[test]
+	fn scan_loops() {
+		assert_eq!(ops(b"+[>]").unwrap(), vec![basic(Token::Add, 1), Op::ScanRight]);
+		assert_eq!(ops(b">[<]").unwrap(), vec![basic(Token::Right, 1), Op::ScanLeft]);
+	}
 }
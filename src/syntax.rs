@@ -1,4 +1,4 @@
-use std::fmt;
+use core::fmt;
 
 #[derive(Copy, Clone, Eq, PartialEq, Debug)]
 pub enum Token {